@@ -1,11 +1,16 @@
 //! Battery Simulator — Rust/WASM core
 //!
-//! Three battery strategies running entirely client-side:
-//!   1. DP Optimal — backward dynamic programming (200 SoC bins)
+//! Four battery strategies running entirely client-side, plus an optional
+//! fifth:
+//!   1. DP Optimal — backward dynamic programming (200 SoC bins), perfect foresight
 //!   2. Arbitrage — P33/P67 daily percentile heuristic
 //!   3. Self-consumption — charge excess PV, discharge to offset import
+//!   4. MPC — receding-horizon DP driven by historical-analogue forecasts
+//!   5. Stochastic (optional) — scenario DP minimizing expected cost across
+//!      caller-supplied price scenarios, run only when `price_scenarios`/
+//!      `scenario_weights` are given
 //!
-//! The single WASM export `simulate(days_json, params_json)` runs all three
+//! The single WASM export `simulate(days_json, params_json)` runs all four
 //! strategies plus a no-battery baseline and returns JSON with SoC traces
 //! and costs for Chart.js rendering.
 
@@ -30,6 +35,23 @@ struct BatteryParams {
     soc_min_pct: f64,       // minimum SoC as % of capacity
     soc_max_pct: f64,       // maximum SoC as % of capacity
     export_coeff: f64,      // export revenue multiplier (0-1, accounts for grid fees)
+    charge_eff: f64,        // charging round-trip share (0-1): grid Wh -> stored Wh
+    discharge_eff: f64,     // discharging round-trip share (0-1): stored Wh -> house Wh
+    self_discharge_pct_per_hour: f64,  // standing SoC loss per hour, e.g. 0.1 = 0.1%/h
+    degradation_cost_pln_per_kwh: f64, // wear cost per kWh of charge/discharge throughput
+    #[serde(default)]
+    capex_pln: Option<f64>,             // upfront battery cost, for payback estimation
+    #[serde(default)]
+    lifetime_cycles: Option<f64>,       // rated full-cycle life, for remaining-life estimation
+    // Optional price-uncertainty scenarios for the stochastic DP. Each inner
+    // vec must be as long as the flattened hourly data; weights must be the
+    // same length as `price_scenarios` and sum to 1. Stochastic dispatch is
+    // skipped (no `stochastic`/`stochastic_financial` in the result) unless
+    // both are present and consistent.
+    #[serde(default)]
+    price_scenarios: Vec<Vec<f64>>,
+    #[serde(default)]
+    scenario_weights: Vec<f64>,
 }
 
 // ── Output types (serialized back to JS) ─────────────────────────────────────
@@ -39,6 +61,17 @@ struct BatteryParams {
 struct StrategyResult {
     soc_kwh: Vec<f64>,        // SoC after each hour (kWh)
     total_cost_pln: f64,      // net electricity cost over the period
+    discharge_wh: f64,        // total discharge throughput over the period (Wh)
+}
+
+/// Economics of running one strategy instead of going without a battery.
+#[derive(Serialize)]
+struct FinancialSummary {
+    savings_pln: f64,                      // total savings vs no-battery baseline
+    full_cycles: f64,                      // equivalent full charge/discharge cycles
+    avg_daily_savings_pln: f64,
+    payback_days: Option<f64>,             // simple payback period, given `capex_pln`
+    remaining_life_fraction: Option<f64>,  // rated cycle life left after this period, given `lifetime_cycles`
 }
 
 /// Complete simulation result returned to JavaScript.
@@ -50,21 +83,80 @@ struct SimResult {
     price_pln_kwh: Vec<f64>,         // flattened prices (for price/load chart)
     heuristic: StrategyResult,       // P33/P67 arbitrage
     self_consumption: StrategyResult,
-    optimal: StrategyResult,         // DP-optimized schedule
+    optimal: StrategyResult,         // DP-optimized schedule, perfect foresight
+    mpc: StrategyResult,             // receding-horizon DP on historical-analogue forecasts
     no_battery_cost_pln: f64,        // baseline cost without any battery
+    heuristic_financial: FinancialSummary,
+    self_consumption_financial: FinancialSummary,
+    optimal_financial: FinancialSummary,
+    mpc_financial: FinancialSummary,
+    stochastic: Option<StochasticResult>,           // expected dispatch under price uncertainty, if scenarios given
+    stochastic_financial: Option<FinancialSummary>,
+}
+
+/// Compute the economics of one strategy relative to the no-battery baseline.
+fn financial_summary(
+    no_battery_cost_pln: f64,
+    total_cost_pln: f64,
+    discharge_wh: f64,
+    capacity_wh: f64,
+    num_days: f64,
+    capex_pln: Option<f64>,
+    lifetime_cycles: Option<f64>,
+) -> FinancialSummary {
+    let savings_pln = no_battery_cost_pln - total_cost_pln;
+    let full_cycles = if capacity_wh > 0.0 { discharge_wh / capacity_wh } else { 0.0 };
+    let avg_daily_savings_pln = if num_days > 0.0 { savings_pln / num_days } else { 0.0 };
+
+    let payback_days = capex_pln
+        .filter(|_| avg_daily_savings_pln > 0.0)
+        .map(|capex| capex / avg_daily_savings_pln);
+
+    let remaining_life_fraction = lifetime_cycles
+        .filter(|&cycles| cycles > 0.0)
+        .map(|cycles| (1.0 - full_cycles / cycles).max(0.0));
+
+    FinancialSummary {
+        savings_pln,
+        full_cycles,
+        avg_daily_savings_pln,
+        payback_days,
+        remaining_life_fraction,
+    }
 }
 
 // ── Grid cost helper ─────────────────────────────────────────────────────────
 
-/// Compute electricity cost for one hour given net load and battery action.
+/// Floor for `charge_eff`/`discharge_eff`: callers may supply 0 (the
+/// documented range is 0-1) meaning "cannot charge/discharge", but `hour_cost`
+/// divides by `charge_eff` (and self-consumption divides by `discharge_eff`),
+/// so an exact 0 could produce `f64::INFINITY` and break JSON serialization.
+/// Clamp to a negligible-but-finite value instead.
+const MIN_EFFICIENCY: f64 = 1e-6;
+
+/// Compute electricity cost for one hour given net load and the battery's SoC delta.
 ///
 /// cost = (import_W × price - export_W × price × export_coeff) / 1000
 ///
-/// `charge` and `discharge` are the battery's power draw this hour (Wh since 1h slots).
-/// Positive `net` after battery = grid import; negative = grid export.
+/// `charge` and `discharge` are the battery's SoC delta this hour (Wh since 1h
+/// slots; at most one of the two is nonzero). Round-trip losses decouple grid
+/// energy from stored energy: charging the battery by `charge` Wh draws
+/// `charge / charge_eff` Wh from the grid, and discharging it by `discharge`
+/// Wh only offsets `discharge * discharge_eff` Wh of house load. Positive
+/// `net` after battery = grid import; negative = grid export.
 #[inline]
-fn hour_cost(net_load: f64, charge: f64, discharge: f64, price: f64, export_coeff: f64) -> f64 {
-    let net = net_load + charge - discharge;
+fn hour_cost(
+    net_load: f64,
+    charge: f64,
+    discharge: f64,
+    price: f64,
+    export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+) -> f64 {
+    let grid_draw = if charge > 0.0 { charge / charge_eff } else { 0.0 };
+    let house_offset = if discharge > 0.0 { discharge * discharge_eff } else { 0.0 };
+    let net = net_load + grid_draw - house_offset;
     let imp = if net > 0.0 { net } else { 0.0 };
     let exp = if net < 0.0 { -net } else { 0.0 };
     (imp * price - exp * price * export_coeff) / 1000.0
@@ -102,6 +194,10 @@ fn run_heuristic(
     soc_min_wh: f64,
     soc_max_wh: f64,
     export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+    self_discharge_pct_per_hour: f64,
+    degradation_cost_pln_per_kwh: f64,
     initial_soc_wh: f64,
     day_boundaries: &[usize],   // index where each new day starts
 ) -> StrategyResult {
@@ -109,6 +205,8 @@ fn run_heuristic(
     let mut soc_kwh = Vec::with_capacity(t);
     let mut current_soc = initial_soc_wh;
     let mut total_cost = 0.0;
+    let mut discharge_wh = 0.0;
+    let decay_factor = 1.0 - self_discharge_pct_per_hour / 100.0;
 
     // Pre-compute P33/P67 thresholds for each day's price slice
     let mut thresholds: Vec<(f64, f64)> = Vec::new();
@@ -138,6 +236,8 @@ fn run_heuristic(
 
     // Forward simulation: charge/discharge based on price vs thresholds
     for i in 0..t {
+        current_soc *= decay_factor;
+
         let p = price[i];
         let (p33, p67) = thresholds[hour_day[i]];
 
@@ -160,12 +260,15 @@ fn run_heuristic(
 
         current_soc += charge - discharge;
         soc_kwh.push(current_soc / 1000.0);
-        total_cost += hour_cost(net_load[i], charge, discharge, p, export_coeff);
+        total_cost += hour_cost(net_load[i], charge, discharge, p, export_coeff, charge_eff, discharge_eff);
+        total_cost += degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+        discharge_wh += discharge;
     }
 
     StrategyResult {
         soc_kwh,
         total_cost_pln: total_cost,
+        discharge_wh,
     }
 }
 
@@ -182,37 +285,52 @@ fn run_self_consumption(
     soc_min_wh: f64,
     soc_max_wh: f64,
     export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+    self_discharge_pct_per_hour: f64,
+    degradation_cost_pln_per_kwh: f64,
     initial_soc_wh: f64,
 ) -> StrategyResult {
     let t = net_load.len();
     let mut soc_kwh = Vec::with_capacity(t);
     let mut current_soc = initial_soc_wh;
     let mut total_cost = 0.0;
+    let mut discharge_wh = 0.0;
+    let decay_factor = 1.0 - self_discharge_pct_per_hour / 100.0;
 
     for i in 0..t {
+        current_soc *= decay_factor;
+
         let nl = net_load[i];
 
         let charge;
         let discharge;
 
         if nl < 0.0 {
-            // Excess PV production: divert to battery instead of exporting
-            charge = (-nl).min(max_power_w).min(soc_max_wh - current_soc).max(0.0);
+            // Excess PV production: divert to battery instead of exporting.
+            // `charge` is the SoC delta, not the grid-side quantity, so cap
+            // it at the PV surplus converted through charge_eff.
+            charge = ((-nl) * charge_eff).min(max_power_w).min(soc_max_wh - current_soc).max(0.0);
             discharge = 0.0;
         } else {
-            // Net consumption: discharge battery to reduce grid import
+            // Net consumption: discharge battery to reduce grid import.
+            // `discharge` is the SoC delta needed to fully offset `nl` Wh of
+            // import, i.e. `nl / discharge_eff` before losses.
             charge = 0.0;
-            discharge = nl.min(max_power_w).min(current_soc - soc_min_wh).max(0.0);
+            discharge = (nl / discharge_eff).min(max_power_w).min(current_soc - soc_min_wh).max(0.0);
         }
 
         current_soc += charge - discharge;
         soc_kwh.push(current_soc / 1000.0);
-        total_cost += hour_cost(nl, charge, discharge, price[i], export_coeff);
+        total_cost += hour_cost(nl, charge, discharge, price[i], export_coeff, charge_eff, discharge_eff);
+        total_cost += degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+        discharge_wh += discharge;
     }
 
     StrategyResult {
         soc_kwh,
         total_cost_pln: total_cost,
+        discharge_wh,
     }
 }
 
@@ -245,6 +363,10 @@ fn run_optimal(
     soc_min_wh: f64,
     soc_max_wh: f64,
     export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+    self_discharge_pct_per_hour: f64,
+    degradation_cost_pln_per_kwh: f64,
     initial_soc_wh: f64,
 ) -> StrategyResult {
     let t = net_load.len();
@@ -252,6 +374,7 @@ fn run_optimal(
         return StrategyResult {
             soc_kwh: vec![],
             total_cost_pln: 0.0,
+            discharge_wh: 0.0,
         };
     }
 
@@ -260,14 +383,16 @@ fn run_optimal(
         // Degenerate case: no usable capacity
         let mut total = 0.0;
         for i in 0..t {
-            total += hour_cost(net_load[i], 0.0, 0.0, price[i], export_coeff);
+            total += hour_cost(net_load[i], 0.0, 0.0, price[i], export_coeff, charge_eff, discharge_eff);
         }
         return StrategyResult {
             soc_kwh: vec![soc_min_wh / 1000.0; t],
             total_cost_pln: total,
+            discharge_wh: 0.0,
         };
     }
 
+    let decay_factor = 1.0 - self_discharge_pct_per_hour / 100.0;
     let bin_wh = soc_range / N_BINS as f64;  // Wh per bin
 
     // Conversion helpers between bin index and Wh
@@ -302,7 +427,8 @@ fn run_optimal(
         let p = price[hour];
 
         for s in 0..=N_BINS {
-            let soc_wh = bin_to_wh(s);
+            // Self-discharge drifts SoC down before the charge/discharge decision.
+            let soc_wh = bin_to_wh(s) * decay_factor;
             let mut best_cost = inf;
             let mut best_next = s as u16;
 
@@ -320,7 +446,9 @@ fn run_optimal(
                     (0.0, -delta)
                 };
 
-                let cost = hour_cost(nl, charge, discharge, p, export_coeff) + dp_next[s2];
+                let cost = hour_cost(nl, charge, discharge, p, export_coeff, charge_eff, discharge_eff)
+                    + degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0
+                    + dp_next[s2];
 
                 if cost < best_cost {
                     best_cost = cost;
@@ -340,10 +468,11 @@ fn run_optimal(
     let mut soc_kwh = Vec::with_capacity(t);
     let mut current_bin = wh_to_bin(initial_soc_wh);
     let mut total_cost = 0.0;
+    let mut discharge_wh = 0.0;
 
     for hour in 0..t {
         let next_bin = policy[hour][current_bin] as usize;
-        let soc_wh = bin_to_wh(current_bin);
+        let soc_wh = bin_to_wh(current_bin) * decay_factor;
         let soc2_wh = bin_to_wh(next_bin);
         let delta = soc2_wh - soc_wh;
 
@@ -353,7 +482,9 @@ fn run_optimal(
             (0.0, -delta)
         };
 
-        total_cost += hour_cost(net_load[hour], charge, discharge, price[hour], export_coeff);
+        total_cost += hour_cost(net_load[hour], charge, discharge, price[hour], export_coeff, charge_eff, discharge_eff);
+        total_cost += degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+        discharge_wh += discharge;
         soc_kwh.push(soc2_wh / 1000.0);
         current_bin = next_bin;
     }
@@ -361,6 +492,255 @@ fn run_optimal(
     StrategyResult {
         soc_kwh,
         total_cost_pln: total_cost,
+        discharge_wh,
+    }
+}
+
+// ── Stochastic scenario DP (decision under price uncertainty) ───────────────
+//
+// `run_optimal` above optimizes against one known price vector — a hindsight
+// bound. This variant instead takes K price scenarios with probability
+// weights (net load is shared; only price differs per scenario) and finds
+// the single non-anticipative policy that minimizes probability-weighted
+// expected cost, i.e. the best schedule implementable without knowing which
+// scenario will materialize. It reuses the same backward-DP shape as
+// `run_optimal`: the only difference is that each transition's immediate
+// cost is the weighted sum of `hour_cost` across scenarios rather than a
+// single scenario's cost. Because the policy (and therefore the SoC path)
+// is identical across scenarios, only the realized per-scenario cost
+// differs — reported alongside the expected cost so callers can see risk.
+
+#[derive(Serialize)]
+struct StochasticResult {
+    soc_kwh: Vec<f64>,             // SoC path implied by the policy (same across scenarios)
+    expected_cost_pln: f64,        // probability-weighted expected cost
+    scenario_cost_pln: Vec<f64>,   // realized cost under each scenario, for risk spread
+    discharge_wh: f64,             // total discharge throughput over the period (Wh)
+}
+
+fn run_optimal_stochastic(
+    net_load: &[f64],
+    price_scenarios: &[Vec<f64>],
+    weights: &[f64],
+    max_power_w: f64,
+    soc_min_wh: f64,
+    soc_max_wh: f64,
+    export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+    self_discharge_pct_per_hour: f64,
+    degradation_cost_pln_per_kwh: f64,
+    initial_soc_wh: f64,
+) -> StochasticResult {
+    let t = net_load.len();
+    let k = price_scenarios.len();
+
+    if t == 0 || k == 0 {
+        return StochasticResult {
+            soc_kwh: vec![],
+            expected_cost_pln: 0.0,
+            scenario_cost_pln: vec![0.0; k],
+            discharge_wh: 0.0,
+        };
+    }
+
+    let soc_range = soc_max_wh - soc_min_wh;
+    if soc_range <= 0.0 {
+        // Degenerate case: no usable capacity
+        let mut scenario_cost_pln = vec![0.0; k];
+        for hour in 0..t {
+            for (s, cost) in scenario_cost_pln.iter_mut().enumerate() {
+                *cost += hour_cost(net_load[hour], 0.0, 0.0, price_scenarios[s][hour], export_coeff, charge_eff, discharge_eff);
+            }
+        }
+        let expected_cost_pln = weights.iter().zip(&scenario_cost_pln).map(|(w, c)| w * c).sum();
+        return StochasticResult {
+            soc_kwh: vec![soc_min_wh / 1000.0; t],
+            expected_cost_pln,
+            scenario_cost_pln,
+            discharge_wh: 0.0,
+        };
+    }
+
+    let decay_factor = 1.0 - self_discharge_pct_per_hour / 100.0;
+    let bin_wh = soc_range / N_BINS as f64;
+
+    let bin_to_wh = |b: usize| -> f64 { soc_min_wh + b as f64 * bin_wh };
+    let wh_to_bin = |wh: f64| -> usize {
+        let b = ((wh - soc_min_wh) / bin_wh).round() as isize;
+        b.max(0).min(N_BINS as isize) as usize
+    };
+
+    let max_bin_delta = (max_power_w / bin_wh).ceil() as usize;
+    let inf = f64::MAX / 2.0;
+
+    let mut dp_next = vec![inf; N_BINS + 1];
+    let mut dp_curr = vec![inf; N_BINS + 1];
+    let mut policy = vec![vec![0u16; N_BINS + 1]; t];
+
+    for s in 0..=N_BINS {
+        dp_next[s] = 0.0;
+    }
+
+    // ── Backward sweep: minimize probability-weighted expected cost ──
+    for hour in (0..t).rev() {
+        let nl = net_load[hour];
+
+        for s in 0..=N_BINS {
+            let soc_wh = bin_to_wh(s) * decay_factor;
+            let mut best_cost = inf;
+            let mut best_next = s as u16;
+
+            let s_lo = if s >= max_bin_delta { s - max_bin_delta } else { 0 };
+            let s_hi = (s + max_bin_delta).min(N_BINS);
+
+            for s2 in s_lo..=s_hi {
+                let soc2_wh = bin_to_wh(s2);
+                let delta = soc2_wh - soc_wh;
+
+                let (charge, discharge) = if delta >= 0.0 {
+                    (delta, 0.0)
+                } else {
+                    (0.0, -delta)
+                };
+
+                let mut expected_step_cost = 0.0;
+                for (scenario, &weight) in price_scenarios.iter().zip(weights) {
+                    expected_step_cost += weight * hour_cost(nl, charge, discharge, scenario[hour], export_coeff, charge_eff, discharge_eff);
+                }
+                expected_step_cost += degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+
+                let cost = expected_step_cost + dp_next[s2];
+
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_next = s2 as u16;
+                }
+            }
+
+            dp_curr[s] = best_cost;
+            policy[hour][s] = best_next;
+        }
+
+        std::mem::swap(&mut dp_curr, &mut dp_next);
+    }
+
+    // ── Forward trace: the policy is scenario-independent, so there is a
+    // single SoC path; only the realized cost is evaluated per scenario ──
+    let mut soc_kwh = Vec::with_capacity(t);
+    let mut current_bin = wh_to_bin(initial_soc_wh);
+    let mut scenario_cost_pln = vec![0.0; k];
+    let mut discharge_wh = 0.0;
+
+    for hour in 0..t {
+        let next_bin = policy[hour][current_bin] as usize;
+        let soc_wh = bin_to_wh(current_bin) * decay_factor;
+        let soc2_wh = bin_to_wh(next_bin);
+        let delta = soc2_wh - soc_wh;
+
+        let (charge, discharge) = if delta >= 0.0 {
+            (delta, 0.0)
+        } else {
+            (0.0, -delta)
+        };
+
+        let degradation = degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+        for (s, cost) in scenario_cost_pln.iter_mut().enumerate() {
+            *cost += hour_cost(net_load[hour], charge, discharge, price_scenarios[s][hour], export_coeff, charge_eff, discharge_eff) + degradation;
+        }
+
+        discharge_wh += discharge;
+        soc_kwh.push(soc2_wh / 1000.0);
+        current_bin = next_bin;
+    }
+
+    let expected_cost_pln = weights.iter().zip(&scenario_cost_pln).map(|(w, c)| w * c).sum();
+
+    StochasticResult {
+        soc_kwh,
+        expected_cost_pln,
+        scenario_cost_pln,
+        discharge_wh,
+    }
+}
+
+// ── Strategy 4: MPC (receding horizon, historical-analogue forecasts) ───────
+//
+// The DP optimal strategy above assumes perfect foresight, which is an
+// unachievable lower bound. This strategy instead re-plans every hour using
+// only a forecast of the next `MPC_HORIZON_HOURS`, built by the simplest
+// analogue method: assume each future hour repeats the same hour of the
+// previous day (`net_load[t-24]`, `price[t-24]`), falling back to zero before
+// 24h of history exists.
+//
+// At each hour we solve the existing DP on the forecast window starting from
+// the real current SoC, commit only the first hour's action against the
+// *actual* net load and price, advance SoC, and slide the window forward.
+
+const MPC_HORIZON_HOURS: usize = 24;
+
+fn run_mpc(
+    net_load: &[f64],
+    price: &[f64],
+    max_power_w: f64,
+    soc_min_wh: f64,
+    soc_max_wh: f64,
+    export_coeff: f64,
+    charge_eff: f64,
+    discharge_eff: f64,
+    self_discharge_pct_per_hour: f64,
+    degradation_cost_pln_per_kwh: f64,
+    initial_soc_wh: f64,
+) -> StrategyResult {
+    let t = net_load.len();
+    let mut soc_kwh = Vec::with_capacity(t);
+    let mut current_soc = initial_soc_wh;
+    let mut total_cost = 0.0;
+    let mut discharge_wh = 0.0;
+    let decay_factor = 1.0 - self_discharge_pct_per_hour / 100.0;
+
+    for h in 0..t {
+        let window_len = MPC_HORIZON_HOURS.min(t - h);
+        let mut forecast_net = Vec::with_capacity(window_len);
+        let mut forecast_price = Vec::with_capacity(window_len);
+        for i in 0..window_len {
+            let src = (h + i) as isize - MPC_HORIZON_HOURS as isize;
+            if src >= 0 {
+                forecast_net.push(net_load[src as usize]);
+                forecast_price.push(price[src as usize]);
+            } else {
+                forecast_net.push(0.0);
+                forecast_price.push(0.0);
+            }
+        }
+
+        // `run_optimal` applies self-discharge internally, so pass the
+        // pre-decay SoC (same convention as every other caller) and apply
+        // the single decay locally when computing the committed delta.
+        let plan = run_optimal(
+            &forecast_net, &forecast_price,
+            max_power_w, soc_min_wh, soc_max_wh,
+            export_coeff, charge_eff, discharge_eff,
+            self_discharge_pct_per_hour, degradation_cost_pln_per_kwh, current_soc,
+        );
+
+        // Commit only the first hour of the plan against the real data.
+        let decayed_soc = current_soc * decay_factor;
+        let planned_next_wh = plan.soc_kwh[0] * 1000.0;
+        let delta = planned_next_wh - decayed_soc;
+        let (charge, discharge) = if delta >= 0.0 { (delta, 0.0) } else { (0.0, -delta) };
+
+        total_cost += hour_cost(net_load[h], charge, discharge, price[h], export_coeff, charge_eff, discharge_eff);
+        total_cost += degradation_cost_pln_per_kwh * (charge + discharge) / 1000.0;
+        discharge_wh += discharge;
+        current_soc = decayed_soc + charge - discharge;
+        soc_kwh.push(current_soc / 1000.0);
+    }
+
+    StrategyResult {
+        soc_kwh,
+        discharge_wh,
+        total_cost_pln: total_cost,
     }
 }
 
@@ -370,7 +750,7 @@ fn run_optimal(
 fn no_battery_cost(net_load: &[f64], price: &[f64], export_coeff: f64) -> f64 {
     let mut total = 0.0;
     for i in 0..net_load.len() {
-        total += hour_cost(net_load[i], 0.0, 0.0, price[i], export_coeff);
+        total += hour_cost(net_load[i], 0.0, 0.0, price[i], export_coeff, 1.0, 1.0);
     }
     total
 }
@@ -383,7 +763,12 @@ fn no_battery_cost(net_load: &[f64], price: &[f64], export_coeff: f64) -> f64 {
 /// returns a JSON string with SoC traces and cost comparisons.
 ///
 /// Input `days_json`: `[{date, net_load_w: [f64], price_pln_kwh: [f64]}, ...]`
-/// Input `params_json`: `{capacity_kwh, max_power_w, soc_min_pct, soc_max_pct, export_coeff}`
+/// Input `params_json`: `{capacity_kwh, max_power_w, soc_min_pct, soc_max_pct, export_coeff,
+/// charge_eff, discharge_eff, self_discharge_pct_per_hour, degradation_cost_pln_per_kwh,
+/// capex_pln?, lifetime_cycles?, price_scenarios?: [[f64]], scenario_weights?: [f64]}`.
+/// `price_scenarios`/`scenario_weights` are optional; when present and consistent
+/// (each scenario as long as the flattened hourly data, weights the same length as
+/// scenarios), the result also includes `stochastic`/`stochastic_financial`.
 #[wasm_bindgen]
 pub fn simulate(days_json: &str, params_json: &str) -> String {
     let days: Vec<DayData> = serde_json::from_str(days_json).unwrap_or_default();
@@ -393,6 +778,14 @@ pub fn simulate(days_json: &str, params_json: &str) -> String {
         soc_min_pct: 10.0,
         soc_max_pct: 90.0,
         export_coeff: 0.8,
+        charge_eff: 1.0,
+        discharge_eff: 1.0,
+        self_discharge_pct_per_hour: 0.0,
+        degradation_cost_pln_per_kwh: 0.0,
+        capex_pln: None,
+        lifetime_cycles: None,
+        price_scenarios: vec![],
+        scenario_weights: vec![],
     });
 
     // Flatten multi-day data into contiguous arrays for simulation.
@@ -415,27 +808,76 @@ pub fn simulate(days_json: &str, params_json: &str) -> String {
     let soc_max_wh = capacity_wh * params.soc_max_pct / 100.0;
     let initial_soc = soc_min_wh;  // start at minimum SoC
 
-    // Run all three strategies on the same data
+    // hour_cost divides by charge_eff and self-consumption by discharge_eff,
+    // so a caller-supplied 0 (a "cannot
+    // charge/discharge at all" edge of the documented 0-1 range) would
+    // produce non-finite costs; floor them at a negligible efficiency instead.
+    let charge_eff = params.charge_eff.max(MIN_EFFICIENCY);
+    let discharge_eff = params.discharge_eff.max(MIN_EFFICIENCY);
+
+    // Run all four strategies on the same data
     let heuristic = run_heuristic(
         &all_net_load, &all_price,
         params.max_power_w, soc_min_wh, soc_max_wh,
-        params.export_coeff, initial_soc, &day_boundaries,
+        params.export_coeff, charge_eff, discharge_eff,
+        params.self_discharge_pct_per_hour, params.degradation_cost_pln_per_kwh,
+        initial_soc, &day_boundaries,
     );
 
     let self_consumption = run_self_consumption(
         &all_net_load, &all_price,
         params.max_power_w, soc_min_wh, soc_max_wh,
-        params.export_coeff, initial_soc,
+        params.export_coeff, charge_eff, discharge_eff,
+        params.self_discharge_pct_per_hour, params.degradation_cost_pln_per_kwh,
+        initial_soc,
     );
 
     let optimal = run_optimal(
         &all_net_load, &all_price,
         params.max_power_w, soc_min_wh, soc_max_wh,
-        params.export_coeff, initial_soc,
+        params.export_coeff, charge_eff, discharge_eff,
+        params.self_discharge_pct_per_hour, params.degradation_cost_pln_per_kwh,
+        initial_soc,
+    );
+
+    let mpc = run_mpc(
+        &all_net_load, &all_price,
+        params.max_power_w, soc_min_wh, soc_max_wh,
+        params.export_coeff, charge_eff, discharge_eff,
+        params.self_discharge_pct_per_hour, params.degradation_cost_pln_per_kwh,
+        initial_soc,
     );
 
     let no_batt_cost = no_battery_cost(&all_net_load, &all_price, params.export_coeff);
 
+    // Economics per strategy — savings, cycle throughput, and payback
+    let num_days = days.len() as f64;
+    let heuristic_financial = financial_summary(no_batt_cost, heuristic.total_cost_pln, heuristic.discharge_wh, capacity_wh, num_days, params.capex_pln, params.lifetime_cycles);
+    let self_consumption_financial = financial_summary(no_batt_cost, self_consumption.total_cost_pln, self_consumption.discharge_wh, capacity_wh, num_days, params.capex_pln, params.lifetime_cycles);
+    let optimal_financial = financial_summary(no_batt_cost, optimal.total_cost_pln, optimal.discharge_wh, capacity_wh, num_days, params.capex_pln, params.lifetime_cycles);
+    let mpc_financial = financial_summary(no_batt_cost, mpc.total_cost_pln, mpc.discharge_wh, capacity_wh, num_days, params.capex_pln, params.lifetime_cycles);
+
+    // Stochastic dispatch under price uncertainty, only when the caller supplied
+    // consistent scenarios (same length as the flattened data) and weights.
+    let stochastic = if !params.price_scenarios.is_empty()
+        && params.scenario_weights.len() == params.price_scenarios.len()
+        && params.price_scenarios.iter().all(|s| s.len() == all_net_load.len())
+    {
+        Some(run_optimal_stochastic(
+            &all_net_load, &params.price_scenarios, &params.scenario_weights,
+            params.max_power_w, soc_min_wh, soc_max_wh,
+            params.export_coeff, charge_eff, discharge_eff,
+            params.self_discharge_pct_per_hour, params.degradation_cost_pln_per_kwh,
+            initial_soc,
+        ))
+    } else {
+        None
+    };
+
+    let stochastic_financial = stochastic.as_ref().map(|s| {
+        financial_summary(no_batt_cost, s.expected_cost_pln, s.discharge_wh, capacity_wh, num_days, params.capex_pln, params.lifetime_cycles)
+    });
+
     // Pack everything into JSON for the frontend
     let result = SimResult {
         hours: all_net_load.len(),
@@ -445,7 +887,14 @@ pub fn simulate(days_json: &str, params_json: &str) -> String {
         heuristic,
         self_consumption,
         optimal,
+        mpc,
         no_battery_cost_pln: no_batt_cost,
+        heuristic_financial,
+        self_consumption_financial,
+        optimal_financial,
+        mpc_financial,
+        stochastic,
+        stochastic_financial,
     };
 
     serde_json::to_string(&result).unwrap_or_default()